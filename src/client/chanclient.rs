@@ -1,3 +1,4 @@
+use std::collections::HashMap;
 use std::sync::mpsc::{channel, Receiver, Sender};
 
 use ndarray::Array2;
@@ -22,6 +23,8 @@ pub enum ClientMessage {
     NodeMeta(NodeHandle, EffectMeta),
     /// node_id(handle, id) call
     NodeId(NodeHandle, EffectId),
+    /// node_meters(meters) call
+    NodeMeters(HashMap<NodeHandle, f32>),
 }
 
 impl MpscClient {
@@ -47,4 +50,7 @@ impl Client for MpscClient {
     fn node_id(&mut self, handle: &NodeHandle, id: &EffectId) {
         self.send(ClientMessage::NodeId(*handle, id.clone()));
     }
+    fn node_meters(&mut self, meters: HashMap<NodeHandle, f32>) {
+        self.send(ClientMessage::NodeMeters(meters));
+    }
 }
@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+
 use ndarray::Array2;
 
 use routing::{NodeHandle, EffectMeta, EffectId};
@@ -12,4 +14,7 @@ pub trait Client {
     fn node_meta(&mut self, _handle: &NodeHandle, _meta: &EffectMeta) {}
     /// Response to a query of a node's id
     fn node_id(&mut self, _handle: &NodeHandle, _id: &EffectId) {}
+    /// Peak level of each toplevel node during a metered render.
+    /// See `Renderer::fill_buffer_metered` for what exactly is measured.
+    fn node_meters(&mut self, _meters: HashMap<NodeHandle, f32>) {}
 }
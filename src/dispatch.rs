@@ -2,8 +2,11 @@
 //! something cohesive. It effectively hides the rest of the library,
 //! and all commands are meant to pass through this instead.
 
-use std::path::Path;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 use std::ops::Range;
+use std::rc::Rc;
 
 use jagged_array::Jagged2;
 use ndarray::{ArrayBase, Dim};
@@ -11,10 +14,12 @@ use ndarray::{ArrayBase, Dim};
 use client::Client;
 use render::Renderer;
 use resman::ResMan;
-use routing::{Edge, Effect, NodeData, NodeHandle, RouteGraph, EffectId};
+use routing::{Edge, EdgeWeight, Effect, NodeData, NodeHandle, RouteGraph, EffectId};
 use routing::{effect, routegraph};
+use routing::effect::{EffectData, PrimitiveEffect};
+use stdfx;
 
-#[derive(Default, Debug)]
+#[derive(Debug)]
 pub struct Dispatch<R, C> {
     /// Contains the toplevel description of the audio being generated.
     routegraph: RouteGraph,
@@ -25,6 +30,9 @@ pub struct Dispatch<R, C> {
     /// Where to send notifications of state changes,
     /// results from the renderer, etc.
     client: C,
+    /// Effects whose definitions have changed on disk and need to be reloaded.
+    /// Populated by the callback `Dispatch::new` registers with `resman`.
+    changed_effects: Rc<RefCell<Vec<EffectId>>>,
 }
 
 /// OSC message to /<...>
@@ -54,6 +62,13 @@ pub enum OscRouteGraph {
     DelNode((), (NodeHandle,)),
     #[osc_address(address="del_edge")]
     DelEdge((), (Edge,)),
+    /// Delete a node along with all the edges that touch it.
+    #[osc_address(address="del_node_cascade")]
+    DelNodeCascade((), (NodeHandle,)),
+    /// Set the value of a parameter, i.e. the F32Constant that drives
+    /// the given input slot of the given node.
+    #[osc_address(address="set_param")]
+    SetParam((), (NodeHandle, u32, f32)),
     /// Query a node's metadata: its I/Os, etc.
     #[osc_address(address="query_meta")]
     QueryMeta((), (NodeHandle,)),
@@ -74,6 +89,13 @@ pub enum OscRenderer {
     /// TODO: second argument should be Jagged2; not Vec<Vec<f32>>
     #[osc_address(address="render")]
     RenderRange((), (Range<u64>, u32, Jagged2<f32>)),
+    /// Same as `RenderRange`, but additionally report the peak level
+    /// output by each node over the range via `Client::node_meters`.
+    /// Only values read while rendering are measured, so a node that isn't
+    /// routed to any of the rendered slots reads as 0, even if it's producing
+    /// a signal (see `Renderer::fill_buffer_metered`).
+    #[osc_address(address="render_with_meters")]
+    RenderWithMeters((), (Range<u64>, u32, Jagged2<f32>)),
 }
 
 /// OOSC message to /resman/<...>
@@ -83,6 +105,11 @@ pub enum OscResMan {
     /// Add another directory to watch when loading resources.
     #[osc_address(address="add_dir")]
     AddDir((), (String,)),
+    /// Notify that a file has been modified, so that any nodes using
+    /// an effect defined in that file can be reloaded.
+    /// This is done automatically when the `notify` feature is enabled.
+    #[osc_address(address="notify_changed")]
+    NotifyChanged((), (String,)),
 }
 
 
@@ -90,6 +117,8 @@ pub enum OscResMan {
 pub enum Error {
     RouteGraphError(routegraph::Error),
     EffectError(effect::Error),
+    /// The given input slot of the given node isn't driven by a F32Constant.
+    NoSuchParam(NodeHandle, u32),
 }
 
 type ResultE<T> = Result<T, Error>;
@@ -97,19 +126,42 @@ type ResultE<T> = Result<T, Error>;
 
 impl<R, C> Dispatch<R, C> {
     pub fn new(renderer: R, client: C) -> Self {
+        let changed_effects: Rc<RefCell<Vec<EffectId>>> = Default::default();
+        let mut resman = ResMan::new();
+        stdfx::register_all(&mut resman);
+        {
+            let changed_effects = changed_effects.clone();
+            resman.watch(move |id| changed_effects.borrow_mut().push(id));
+        }
         Self {
             routegraph: Default::default(),
             renderer,
-            resman: Default::default(),
+            resman,
             client,
+            changed_effects,
         }
     }
 }
 
+/// Default construction has to go through `new`, so that effect reloading
+/// gets hooked up.
+impl<R: Default, C: Default> Default for Dispatch<R, C> {
+    fn default() -> Self {
+        Self::new(Default::default(), Default::default())
+    }
+}
+
 impl<R: Renderer, C: Client> Dispatch<R, C> {
     /// Process the OSC message.
     pub fn dispatch(&mut self, msg: OscToplevel) -> ResultE<()> {
         trace!("Dispatching message: {:?}", msg);
+        self.resman.poll_changes();
+        let result = self.handle_msg(msg);
+        // Reload effects even if the message failed, so they don't linger in the queue.
+        self.reload_changed_effects();
+        result
+    }
+    fn handle_msg(&mut self, msg: OscToplevel) -> ResultE<()> {
         match msg {
             OscToplevel::RouteGraph((), rg_msg) => match rg_msg {
                 OscRouteGraph::AddNode((), (handle, id)) => {
@@ -129,6 +181,22 @@ impl<R: Renderer, C: Client> Dispatch<R, C> {
                     self.routegraph.del_edge(edge.clone());
                     self.on_del_edge(&edge);
                 }
+                OscRouteGraph::DelNodeCascade((), (handle,)) => {
+                    let edges = self.routegraph.remove_node_cascade(handle)?;
+                    for edge in &edges {
+                        self.on_del_edge(edge);
+                    }
+                    self.on_del_node(&handle);
+                }
+                OscRouteGraph::SetParam((), (handle, slot, value)) => {
+                    let old_edge = self.find_param_edge(&handle, slot)
+                        .ok_or(Error::NoSuchParam(handle, slot))?;
+                    let new_edge = Edge::new(old_edge.from_full(), handle,
+                        EdgeWeight::new(value.to_bits(), slot));
+                    self.routegraph.replace_edge(old_edge.clone(), new_edge.clone())?;
+                    self.on_del_edge(&old_edge);
+                    self.on_add_edge(&new_edge);
+                }
                 OscRouteGraph::QueryMeta((), (handle,)) => {
                     if let Some(effect) = self.routegraph.get_data(&handle) {
                         self.client.node_meta(&handle, effect.meta());
@@ -150,15 +218,106 @@ impl<R: Renderer, C: Client> Dispatch<R, C> {
                     self.renderer.fill_buffer(&mut buff, range.start, inputs);
                     self.client.audio_rendered(buff, range.start);
                 }
+                OscRenderer::RenderWithMeters((), (range, num_slots, inputs)) => {
+                    let mut buff = ArrayBase::zeros(Dim([num_slots as usize, (range.end-range.start) as usize]));
+                    let meters = self.renderer.fill_buffer_metered(&mut buff, range.start, inputs);
+                    self.client.audio_rendered(buff, range.start);
+                    self.client.node_meters(meters);
+                }
             },
             OscToplevel::ResMan((), res_msg) => match res_msg {
                 OscResMan::AddDir((), (dir,)) => {
                     self.resman.add_dir(Path::new(&dir).to_path_buf());
                 }
+                OscResMan::NotifyChanged((), (path,)) => {
+                    self.resman.notify_changed(Path::new(&path));
+                }
             }
         }
         Ok(())
     }
+    /// Reload every effect whose definition changed on disk, and swap the
+    /// new definition into each node that uses it, directly or as a sub-effect.
+    fn reload_changed_effects(&mut self) {
+        // Group the changed ids by the file they were loaded from, so each file is only reloaded once.
+        let mut changed: HashMap<PathBuf, Vec<EffectId>> = HashMap::new();
+        for id in self.changed_effects.borrow_mut().drain(..) {
+            if let Some(path) = self.resman.path_of(&id) {
+                changed.entry(path).or_insert_with(Vec::new).push(id);
+            }
+        }
+        for (path, old_ids) in changed {
+            let new_data = match Effect::from_path(old_ids[0].clone(), &path, &self.resman) {
+                Ok(node_data) => node_data,
+                Err(error) => {
+                    warn!("Unable to reload effect {:?}: {:?}", old_ids[0].name(), error);
+                    continue;
+                }
+            };
+            let mut is_reload_complete = true;
+            for old_id in &old_ids {
+                let mut replacements: Vec<(NodeHandle, NodeData)> = Vec::new();
+                for (handle, data) in self.routegraph.iter_nodes() {
+                    if data.id().same_definition(old_id) {
+                        replacements.push((*handle, new_data.clone()));
+                    } else {
+                        match data.with_subeffect_replaced(old_id, &new_data) {
+                            Ok(Some(replaced)) => replacements.push((*handle, replaced)),
+                            Ok(None) => {},
+                            Err(error) => {
+                                warn!("Unable to reload node {:?}: {:?}", handle, error);
+                                is_reload_complete = false;
+                            }
+                        }
+                    }
+                }
+                for (handle, node_data) in replacements {
+                    is_reload_complete &= self.reload_node(handle, node_data);
+                }
+            }
+            // Nodes that failed to reload still use the old ids, so keep watching for those.
+            if is_reload_complete {
+                self.resman.notify_effect_reloaded(&path, new_data.id().clone());
+            }
+        }
+    }
+    /// Swap the data of an existing node and inform the renderer.
+    /// Returns false (and leaves the node as-is) if the new data isn't compatible with the node's edges.
+    fn reload_node(&mut self, handle: NodeHandle, node_data: NodeData) -> bool {
+        let edges: Vec<Edge> = self.routegraph.iter_edges_to(&handle)
+            .chain(self.routegraph.iter_edges_from(&handle))
+            .cloned().collect();
+        match self.routegraph.replace_node(handle, node_data.clone()) {
+            Ok(_) => {
+                // Renderers have no notion of replacing a node; recreate it instead.
+                for edge in &edges {
+                    self.on_del_edge(edge);
+                }
+                self.on_del_node(&handle);
+                self.on_add_node(&handle, &node_data);
+                for edge in &edges {
+                    self.on_add_edge(edge);
+                }
+                true
+            },
+            Err(error) => {
+                warn!("Unable to reload node {:?}: {:?}", handle, error);
+                false
+            }
+        }
+    }
+    /// Find the edge that carries a F32Constant into the given slot of `handle`.
+    fn find_param_edge(&self, handle: &NodeHandle, slot: u32) -> Option<Edge> {
+        let routegraph = &self.routegraph;
+        routegraph.iter_edges_to(handle).find(|edge| {
+            edge.to_slot() == slot && routegraph.get_data(&edge.from_full()).map_or(false, |data| {
+                match *data.data() {
+                    EffectData::Primitive(PrimitiveEffect::F32Constant) => true,
+                    _ => false,
+                }
+            })
+        }).cloned()
+    }
 }
 
 /// Conversion from `routegraph::Error` for use with the `?` operator
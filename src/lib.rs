@@ -9,6 +9,7 @@ extern crate llvm;
 extern crate llvm_sys;
 #[macro_use] extern crate log;
 extern crate ndarray;
+#[cfg(feature="notify")] extern crate notify;
 extern crate num;
 #[macro_use] extern crate osc_address_derive;
 extern crate serde;
@@ -25,6 +26,7 @@ pub mod dispatch;
 pub mod render;
 pub mod routing;
 pub mod resman;
+pub mod stdfx;
 
 
 pub use dispatch::Dispatch;
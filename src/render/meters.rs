@@ -0,0 +1,49 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::ops::Range;
+
+use ndarray::Array2;
+
+use routing::NodeHandle;
+
+/// Tracks the peak absolute value output by each node over a range of time.
+/// Renderers feed this every value they compute for a toplevel node.
+#[derive(Debug)]
+pub struct NodeMeters {
+    /// Values computed outside this range (e.g. reads into the past, by Delay)
+    /// are ignored.
+    times: Range<u64>,
+    peaks: RefCell<HashMap<NodeHandle, f32>>,
+}
+
+impl NodeMeters {
+    /// Meter the given nodes over the given range of time.
+    /// All nodes start with a peak of 0, in case they're never evaluated.
+    pub fn new<'a, I>(times: Range<u64>, nodes: I) -> Self
+        where I: IntoIterator<Item=&'a NodeHandle>
+    {
+        Self {
+            times,
+            peaks: RefCell::new(nodes.into_iter().map(|node| (*node, 0f32)).collect()),
+        }
+    }
+    /// Meter the given nodes over the range of time that `buff` is about to
+    /// be filled with, starting at `idx`.
+    pub fn for_buffer<'a, I>(buff: &Array2<f32>, idx: u64, nodes: I) -> Self
+        where I: IntoIterator<Item=&'a NodeHandle>
+    {
+        let n_times = buff.dim().1;
+        Self::new(idx..idx+n_times as u64, nodes)
+    }
+    /// Record that `node` output `value` at `time`.
+    pub fn record(&self, node: NodeHandle, time: u64, value: f32) {
+        if self.times.start <= time && time < self.times.end {
+            let mut peaks = self.peaks.borrow_mut();
+            let peak = peaks.entry(node).or_insert(0f32);
+            *peak = peak.max(value.abs());
+        }
+    }
+    pub fn into_peaks(self) -> HashMap<NodeHandle, f32> {
+        self.peaks.into_inner()
+    }
+}
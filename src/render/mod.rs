@@ -1,3 +1,4 @@
+mod meters;
 pub mod reference;
 pub mod renderer;
 pub mod sparkle;
@@ -6,6 +6,7 @@ use jagged_array::Jagged2;
 use ndarray::Array2;
 
 use render::Renderer;
+use render::meters::NodeMeters;
 use routing::{Edge, GraphWatcher, NodeData, NodeHandle};
 use routing::effect::{PrimitiveEffect, EffectData};
 use streaming_iterator::StreamingIterator;
@@ -15,6 +16,8 @@ use streaming_iterator::StreamingIterator;
 struct NodeMap {
     nodes: HashMap<NodeHandle, Node>,
     output_edges: Vec<Option<Edge>>,
+    /// Set for the duration of `fill_buffer_metered`.
+    meters: Option<NodeMeters>,
 }
 
 #[derive(Default, Debug)]
@@ -83,6 +86,11 @@ impl Renderer for RefRenderer {
         // Keep track of the playhead
         self.head = idx + n_times as u64;
     }
+    fn fill_buffer_metered(&mut self, buff: &mut Array2<f32>, idx: u64, inputs: Jagged2<f32>) -> HashMap<NodeHandle, f32> {
+        self.nodes.meters = Some(NodeMeters::for_buffer(buff, idx, self.nodes.keys()));
+        self.fill_buffer(buff, idx, inputs);
+        self.nodes.meters.take().unwrap().into_peaks()
+    }
 }
 
 impl RefRenderer {
@@ -184,7 +192,7 @@ impl NodeMap {
         } else {
             // Reading from another node within the DAG
             let node = &self.nodes[&from];
-            match node.data {
+            let value = match node.data {
                 MyNodeData::UserNode(ref new_nodes) => {
                     new_nodes.get_output(time, from_slot, |time2, slot2| {
                         // get the input to this node.
@@ -199,18 +207,17 @@ impl NodeMap {
                         assert!(from_slot == 0);
                         let delay_frames = self.get_maybe_edge_value(time, node.inbound.get(1), get_input);
                         // Clamp delay value to [0, u64::max]
-                        if delay_frames >= 18446744073709551616f32 {
+                        let delay_int = if delay_frames >= 18446744073709551616f32 {
                             // delay is >= than 2^64; must be indexing from negative time.
-                            return 0f32;
-                        }
-                        let delay_int = if delay_frames < 0f32 {
-                            0u64
+                            None
+                        } else if delay_frames < 0f32 {
+                            Some(0u64)
                         } else {
                             // Note: this conversion is flooring.
-                            delay_frames as u64
+                            Some(delay_frames as u64)
                         };
                         // t<0 -> value is 0.
-                        time.checked_sub(delay_int).map_or(0f32, |origin_time| {
+                        delay_int.and_then(|delay_int| time.checked_sub(delay_int)).map_or(0f32, |origin_time| {
                             self.get_maybe_edge_value(origin_time, node.inbound.get(0), get_input)
                         })
                     },
@@ -260,8 +267,18 @@ impl NodeMap {
                             rem
                         }
                     },
+                    PrimitiveEffect::Tanh => {
+                        // The only nonzero output is slot=0.
+                        assert!(from_slot == 0);
+                        let input = self.get_maybe_edge_value(time, node.inbound.get(0), get_input);
+                        input.tanh()
+                    },
                 }
+            };
+            if let Some(ref meters) = self.meters {
+                meters.record(from, time, value);
             }
+            value
         }
     }
 }
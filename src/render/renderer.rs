@@ -1,7 +1,9 @@
+use std::collections::HashMap;
+
 use jagged_array::Jagged2;
 use ndarray::Array2;
 
-use routing::GraphWatcher;
+use routing::{GraphWatcher, NodeHandle};
 /// Trait that allows for rendering a `RouteGraph`
 pub trait Renderer: GraphWatcher {
     /// Fill the provided buffer with samples from a specific slot.
@@ -14,4 +16,17 @@ pub trait Renderer: GraphWatcher {
     /// i.e. it should act as if the inputs into all slots were 0 for all times outside
     /// the range being queried.
     fn fill_buffer(&mut self, buff: &mut Array2<f32>, idx: u64, inputs: Jagged2<f32>);
+    /// Same as `fill_buffer`, but also return the peak absolute value output
+    /// by each toplevel node over the rendered range.
+    ///
+    /// Meters only reflect the values this render actually read from each node
+    /// within the range. So nodes that don't contribute to any of the rendered
+    /// slots report 0, and a node that's only read through a Delay reports its
+    /// delayed values falling inside the range (or 0, if none do).
+    ///
+    /// Renderers that don't support metering report no nodes at all.
+    fn fill_buffer_metered(&mut self, buff: &mut Array2<f32>, idx: u64, inputs: Jagged2<f32>) -> HashMap<NodeHandle, f32> {
+        self.fill_buffer(buff, idx, inputs);
+        HashMap::new()
+    }
 }
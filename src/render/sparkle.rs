@@ -5,9 +5,11 @@
 
 use std::collections::HashMap;
 use std::ffi::CString;
+use std::io::Cursor;
 use std::mem;
 use std::ops::{Deref, DerefMut};
 
+use digest::Digest;
 use jagged_array::Jagged2;
 use llvm;
 use llvm::{Builder, Context, ContextType, ExecutionEngine, Function, Module};
@@ -22,9 +24,11 @@ use llvm_sys::{
 };
 use llvm_sys::prelude::*;
 use ndarray::Array2;
+use sha2::Sha256;
 use streaming_iterator::StreamingIterator;
 
 use render::Renderer;
+use render::meters::NodeMeters;
 use routing::{Edge, Effect, GraphWatcher, NodeData, NodeHandle};
 use routing::effect::{PrimitiveEffect, EffectData};
 
@@ -62,6 +66,8 @@ pub struct SparkleRenderer {
 struct NodeMap {
     nodes: HashMap<NodeHandle, Node>,
     output_edges: Vec<Option<Edge>>,
+    /// Set for the duration of `fill_buffer_metered`.
+    meters: Option<NodeMeters>,
 }
 
 #[derive(Debug)]
@@ -136,6 +142,11 @@ impl Renderer for SparkleRenderer {
         // Keep track of the playhead
         self.head = idx + n_times as u64;
     }
+    fn fill_buffer_metered(&mut self, buff: &mut Array2<f32>, idx: u64, inputs: Jagged2<f32>) -> HashMap<NodeHandle, f32> {
+        self.nodes.meters = Some(NodeMeters::for_buffer(buff, idx, self.nodes.keys()));
+        self.fill_buffer(buff, idx, inputs);
+        self.nodes.meters.take().unwrap().into_peaks()
+    }
 }
 
 impl GraphWatcher for SparkleRenderer {
@@ -167,7 +178,7 @@ impl SparkleRenderer {
     /// callback_type should be { input_getter, userdata },
     /// Returns the name of the function that can be used to get the effect's output.
     fn jit_effect(&mut self, module: &mut Module, effect: &Effect) -> (Function, String) {
-        let fname = format!("{}_get_output", effect.id().name());
+        let fname = format!("{}_get_output", fn_prefix(effect));
         println!("jit: {}", fname);
         let llvm_ctx = Context{ ptr: self.llvm_ctx.ptr };
         let func = match self.get_fn(&fname, Some(module)) {
@@ -187,6 +198,7 @@ impl SparkleRenderer {
                         PrimitiveEffect::Divide => fnbuilder.build_divide(),
                         PrimitiveEffect::Minimum => fnbuilder.build_minimum(),
                         PrimitiveEffect::Modulo => fnbuilder.build_modulo(),
+                        PrimitiveEffect::Tanh => fnbuilder.build_tanh(),
                     },
                     EffectData::RouteGraph(ref graph) => {
                         // Plan: walk the graph depth-first s.t. the inputs to any
@@ -222,7 +234,7 @@ impl SparkleRenderer {
                         for ref node_hnd in graph.iter_nodes_dep_first() {
                             // Create a switch statement that branches on the requested slot (i.e.
                             // to_slot) and maps to from_slot and the appropriate getter function.
-                            let input_get_fname = format!("{}_n{}_get_input", effect.id().name(), node_hnd);
+                            let input_get_fname = format!("{}_n{}_get_input", fn_prefix(effect), node_hnd);
                             let input_get_fn = module.add_function(sample_getter_type, &input_get_fname);
                             let mut input_builder = llvm_ctx.create_builder();
                             let mut input_fnbuilder = FnBuilder::new(input_get_fn, &llvm_ctx, &mut input_builder, &self);
@@ -336,17 +348,44 @@ impl SparkleRenderer {
                 self.get_maybe_edge_value(time2, in_edge)
             };
             let f = node.fnptr.unwrap();
-            unsafe {
+            let value = unsafe {
                 let callback = CallbackType {
                     input_getter: call_closure_from_c as *const fn(u64, u32, *const CallbackType) -> f32,
                     userdata: &mem::transmute(&in_edge_getter as &Fn(u64, u32) -> f32),
                 };
                 f(time, from_slot, &callback)
+            };
+            if let Some(ref meters) = self.nodes.meters {
+                meters.record(from, time, value);
             }
+            value
         }
     }
 }
 
+/// Prefix shared by the names of all LLVM functions generated for an effect.
+/// Includes a hash over the effect's definition and those of its sub-effects,
+/// so that different definitions of the same-named effect (e.g. after editing it,
+/// or one of the effects it's built from) don't alias one another.
+fn fn_prefix(effect: &Effect) -> String {
+    match *effect.data() {
+        EffectData::Primitive(_) => effect.id().name().into(),
+        EffectData::RouteGraph(ref graph) => {
+            let mut definition = effect.id().sha256().as_ref().map_or(Vec::new(), |sha| sha.to_vec());
+            let mut sub_prefixes: Vec<String> = graph.iter_nodes().map(|(_, data)| fn_prefix(data)).collect();
+            sub_prefixes.sort();
+            sub_prefixes.dedup();
+            for prefix in sub_prefixes {
+                definition.extend(prefix.as_bytes());
+                definition.push(0);
+            }
+            let sha = Sha256::digest_reader(&mut Cursor::new(definition)).unwrap();
+            let hex: String = sha.iter().map(|byte| format!("{:02x}", byte)).collect();
+            format!("{}_{}", effect.id().name(), hex)
+        },
+    }
+}
+
 extern "C" fn call_closure_from_c(time: u64, slot: u32, closure_info: *const CallbackType) -> f32 {
     unsafe {
         let closure: &Fn(u64, u32) -> f32 = mem::transmute(*closure_info);
@@ -354,6 +393,11 @@ extern "C" fn call_closure_from_c(time: u64, slot: u32, closure_info: *const Cal
     }
 }
 
+/// Called by the code generated for PrimitiveEffect::Tanh.
+extern "C" fn tanh_from_c(value: f32) -> f32 {
+    value.tanh()
+}
+
 
 impl Default for SparkleRenderer {
     fn default() -> SparkleRenderer {
@@ -508,6 +552,22 @@ impl<'ctx> FnBuilder<'ctx> {
         let result = self.builder.build_select(is_result_neg, result_if_neg, signed_result, "result");
         self.builder.build_ret(result);
     }
+    /// Perform the computations associated with PrimitiveEffect::Tanh
+    fn build_tanh(&mut self) {
+        let time = self.time();
+        let in_getter = self.load_getters();
+        let input = self.read_input(time, 0, in_getter);
+        // LLVM has no tanh instruction, so call back into Rust for it.
+        let f32_type = f32::get_type_in_context(&self.ctx);
+        let tanh_type = llvm::function_type(f32_type, vec![f32_type], /* is_var_arg */false);
+        let tanh_fn = unsafe {
+            let tanh_addr = llvm_sys::core::LLVMConstInt(u64::get_type_in_context(self.ctx),
+                tanh_from_c as usize as u64, /* sign_extend */0);
+            llvm_sys::core::LLVMConstIntToPtr(tanh_addr, llvm::pointer_type(tanh_type, 0))
+        };
+        let result = self.builder.build_call(Function::from_value_ref(tanh_fn), vec![input], "result");
+        self.builder.build_ret(result);
+    }
     /// Unpack the function's `time` argument.
     fn time(&self) -> LLVMValueRef {
         self.func.get_param(0).unwrap()
@@ -1,13 +1,23 @@
 use std::cell::RefCell;
 use std::collections::HashMap;
+use std::fmt;
 use std::fs;
 use std::fs::File;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+#[cfg(feature="notify")]
+use std::sync::mpsc::{channel, Receiver};
+#[cfg(feature="notify")]
+use std::time::Duration;
 
 use digest::Digest;
+#[cfg(feature="notify")]
+use notify;
+#[cfg(feature="notify")]
+use notify::Watcher;
 use sha2::Sha256;
+use url::Url;
 
-use routing::EffectId;
+use routing::{EffectDesc, EffectId};
 
 
 /// Resource manager. Where to search for various file types (e.g. Effects).
@@ -19,12 +29,39 @@ pub struct ResMan {
     dirs: Vec<PathBuf>,
     /// Object that handles indexing/caching files.
     cache: RefCell<ResCache>,
+    /// Who to notify when an effect's definition changes on disk.
+    watcher: ResWatcher,
+    /// Effects that are generated in code, rather than read from a file.
+    std_effects: StdEffects,
 }
 
+/// Builds the description of a standard effect from the url it was requested through.
+/// Returns `None` if the url's parameters are invalid.
+pub type StdEffectBuilder = fn(&Url) -> Option<EffectDesc>;
+
 #[derive(Default, Debug)]
 struct ResCache {
     /// Map sha's to paths.
     sha256_to_path: HashMap<[u8; 32], PathBuf>,
+    /// Map (canonical) paths to the ids of the effects currently loaded from them.
+    /// A file may have been loaded under several ids if it was edited in between.
+    path_to_effects: HashMap<PathBuf, Vec<EffectId>>,
+}
+
+#[derive(Default)]
+struct StdEffects {
+    /// Map effect names to the function that builds them.
+    builders: HashMap<String, StdEffectBuilder>,
+}
+
+#[derive(Default)]
+struct ResWatcher {
+    /// Callbacks to invoke whenever a loaded effect's file changes.
+    callbacks: Vec<Box<FnMut(EffectId)>>,
+    /// OS-level watcher over `dirs`, along with the channel it reports to.
+    /// Only created once somebody calls `watch`.
+    #[cfg(feature="notify")]
+    fs_watcher: Option<(notify::RecommendedWatcher, Receiver<notify::DebouncedEvent>)>,
 }
 
 impl ResMan {
@@ -32,8 +69,69 @@ impl ResMan {
         Default::default()
     }
     pub fn add_dir(&mut self, dir: PathBuf) {
+        self.watcher.watch_dir(&dir);
         self.dirs.push(dir);
     }
+    /// Register a callback to be invoked whenever the file that a previously
+    /// loaded effect came from is changed. The callback receives the id the effect
+    /// was loaded under (i.e. the hash *before* the change); use `path_of` to find
+    /// the file. It's invoked once for each id that was loaded from the file.
+    ///
+    /// With the `notify` feature, all search directories are watched for changes.
+    /// Otherwise, changes must be reported by the host via `notify_changed`.
+    pub fn watch<F: FnMut(EffectId) + 'static>(&mut self, callback: F) {
+        self.watcher.callbacks.push(Box::new(callback));
+        self.watcher.start(&self.dirs);
+    }
+    /// Report that the file at `path` has changed on disk.
+    /// If an effect was loaded from that file, the `watch` callbacks are invoked.
+    pub fn notify_changed(&mut self, path: &Path) {
+        let path = canonicalize(path);
+        let ids = self.cache.borrow().get_effects_by_path(&path).to_vec();
+        for id in ids {
+            trace!("ResMan: effect file changed: {:?} ({:?})", path, id.name());
+            for callback in &mut self.watcher.callbacks {
+                callback(id.clone());
+            }
+        }
+    }
+    /// Deliver any file changes detected by the OS to the `watch` callbacks.
+    /// Without the `notify` feature, this does nothing.
+    pub fn poll_changes(&mut self) {
+        for path in self.watcher.changed_paths() {
+            self.notify_changed(&path);
+        }
+    }
+    /// Record that the effect identified by `id` was loaded from `path`,
+    /// so that it can be reported if the file later changes.
+    pub fn notify_effect_loaded(&self, path: &Path, id: EffectId) {
+        self.cache.borrow_mut().notify_effect(canonicalize(path), id);
+    }
+    /// Record that every user of the file at `path` has been switched over to the
+    /// effect identified by `id`. Unlike `notify_effect_loaded`, this forgets any ids
+    /// previously loaded from the file, so they aren't reported again on later changes.
+    pub fn notify_effect_reloaded(&self, path: &Path, id: EffectId) {
+        self.cache.borrow_mut().replace_effects(canonicalize(path), id);
+    }
+    /// Look up the file that the effect identified by `id` was loaded from.
+    pub fn path_of(&self, id: &EffectId) -> Option<PathBuf> {
+        self.cache.borrow().get_path_by_effect(id).cloned()
+    }
+    /// Make an effect that's generated in code available through urls of the
+    /// form `stdfx:///<name>?<params>`. The url is passed on to `builder`.
+    pub fn register_std_effect(&mut self, name: &str, builder: StdEffectBuilder) {
+        self.std_effects.builders.insert(name.into(), builder);
+    }
+    /// Build the standard effect referred to by one of the id's urls, if any.
+    /// Returns the url that was used along with the effect's description.
+    pub fn find_std_effect(&self, id: &EffectId) -> Option<(Url, EffectDesc)> {
+        id.urls().filter(|url| url.scheme() == "stdfx").filter_map(|url| {
+            let name = url.path().trim_left_matches('/');
+            self.std_effects.builders.get(name).and_then(|builder| {
+                builder(url).map(|desc| (url.clone(), desc))
+            })
+        }).next()
+    }
     /// Returns all definitions of the given effect in the form of an iterator
     ///   over boxed objects implementing io::Read.
     pub fn find_effect<'a>(&'a self, id: &'a EffectId) -> impl Iterator<Item=(PathBuf, File)> + 'a {
@@ -106,6 +204,93 @@ impl ResCache {
     fn get_path_by_sha256(&self, sha256: &[u8; 32]) -> Option<&PathBuf> {
         self.sha256_to_path.get(sha256)
     }
+    /// Call upon successfully loading an effect from a file.
+    fn notify_effect(&mut self, path: PathBuf, id: EffectId) {
+        let ids = self.path_to_effects.entry(path).or_insert_with(Vec::new);
+        if !ids.iter().any(|known| known.same_definition(&id)) {
+            ids.push(id);
+        }
+    }
+    /// Call upon reloading an effect from a file; supersedes all earlier ids.
+    fn replace_effects(&mut self, path: PathBuf, id: EffectId) {
+        self.path_to_effects.insert(path, vec![id]);
+    }
+    /// Look up which effects were loaded from the given file.
+    fn get_effects_by_path(&self, path: &Path) -> &[EffectId] {
+        self.path_to_effects.get(path).map_or(&[], |ids| &ids[..])
+    }
+    /// Look up which file an effect was loaded from.
+    fn get_path_by_effect(&self, id: &EffectId) -> Option<&PathBuf> {
+        self.path_to_effects.iter().find(|&(_, ids)| {
+            ids.iter().any(|known| known.same_definition(id))
+        }).map(|(path, _)| path)
+    }
+}
+
+#[cfg(feature="notify")]
+impl ResWatcher {
+    /// Begin watching the given directories, if not already doing so.
+    fn start(&mut self, dirs: &[PathBuf]) {
+        if self.fs_watcher.is_none() {
+            let (tx, rx) = channel();
+            match notify::watcher(tx, Duration::from_millis(100)) {
+                Ok(fs_watcher) => {
+                    self.fs_watcher = Some((fs_watcher, rx));
+                    for dir in dirs {
+                        self.watch_dir(dir);
+                    }
+                },
+                Err(e) => warn!("ResMan: Failed to create file watcher: {}", e),
+            }
+        }
+    }
+    /// Watch another directory, if watching has been started.
+    fn watch_dir(&mut self, dir: &Path) {
+        if let Some((ref mut fs_watcher, _)) = self.fs_watcher {
+            if let Err(e) = fs_watcher.watch(dir, notify::RecursiveMode::NonRecursive) {
+                warn!("ResMan: Failed to watch directory {:?}: {}", dir, e);
+            }
+        }
+    }
+    /// Drain all the paths that have been written to since the last call.
+    fn changed_paths(&mut self) -> Vec<PathBuf> {
+        self.fs_watcher.iter().flat_map(|&(_, ref rx)| rx.try_iter()).filter_map(|event| {
+            match event {
+                notify::DebouncedEvent::Create(path) |
+                notify::DebouncedEvent::Write(path) |
+                notify::DebouncedEvent::Rename(_, path) => Some(path),
+                _ => None,
+            }
+        }).collect()
+    }
+}
+
+#[cfg(not(feature="notify"))]
+impl ResWatcher {
+    fn start(&mut self, _dirs: &[PathBuf]) {}
+    fn watch_dir(&mut self, _dir: &Path) {}
+    fn changed_paths(&mut self) -> Vec<PathBuf> {
+        Vec::new()
+    }
+}
+
+impl fmt::Debug for StdEffects {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_set().entries(self.builders.keys()).finish()
+    }
+}
+
+impl fmt::Debug for ResWatcher {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "ResWatcher {{ {} callbacks }}", self.callbacks.len())
+    }
+}
+
+/// Resolve symlinks, relative components, etc. so that the same file is always
+/// referred to by the same path, no matter how it was reached.
+/// Paths that can't be resolved (e.g. because the file was deleted) are kept as-is.
+fn canonicalize(path: &Path) -> PathBuf {
+    fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
 }
 
 /// Create a 32-entry array from a slice.
@@ -1,8 +1,11 @@
 use std;
 use std::collections::HashSet;
-use std::io::Cursor;
+use std::fmt;
+use std::fs::File;
+use std::io::{Cursor, Read};
 use std::mem;
 use std::ops::{Deref, Range};
+use std::path::Path;
 use std::rc::Rc;
 
 use digest::Digest;
@@ -12,6 +15,7 @@ use url::Url;
 use url_serde;
 
 use resman::ResMan;
+use super::routegraph;
 use super::routegraph::{Edge, RouteGraph};
 use super::adjlist::AdjList;
 
@@ -34,7 +38,11 @@ pub struct EffectId {
     sha256: Option<[u8; 32]>,
     // TODO: consider a smallset.
     // TODO: use #[serde(with = "url_serde")]
-    /// List of URLs where the Effect can be obtained
+    /// List of URLs where the Effect can be obtained. Besides files, these schemes
+    /// are understood:
+    ///   * `primitive:///<Name>`: implemented directly by the renderer (see `PrimitiveEffect`).
+    ///   * `stdfx:///<Name>?<params>`: one of the standard effects, generated in code
+    ///     by the `ResMan` (see the `stdfx` module). E.g. `stdfx:///MasterBus?inputs=2&drive=1`.
     urls: HashSet<url_serde::Serde<Url>>,
 }
 
@@ -109,6 +117,9 @@ pub enum PrimitiveEffect {
     /// and chosen because Min is more common in linear programming to avoid dealing
     /// with Inf.
     Minimum,
+    /// Primitive effect to calculate the hyperbolic tangent of the input stream.
+    /// Useful for soft-clipping, since it saturates smoothly towards +/-1.
+    Tanh,
 }
 
 /// Iterator over the outputs of a F32Constant primitive effect
@@ -154,69 +165,133 @@ impl Effect {
             }
         }
 
+        // Standard effects are generated by the ResMan, rather than read from disk.
+        if let Some((url, desc)) = resman.find_std_effect(&id) {
+            if let Some(me) = Self::from_desc(id.name(), &url, desc, resman) {
+                if id.sha256 == None || id.sha256 == me.meta.id.sha256 {
+                    return Ok(me);
+                } else {
+                    warn!("Attempted to load a standard Effect, but cannot because of mismatched sha256: {:?}", id.sha256);
+                }
+            }
+        }
+
         // Locate descriptions for non-primitive effects
         for (path, reader) in resman.find_effect(&id) {
-            // Try to deserialize to an effect description
-            let desc: Result<EffectDesc, serde_json::Error> = serde_json::from_reader(reader);
-            match desc {
-                Ok(mut desc) => {
-                    if desc.meta.id.name() == id.name() {
-                        desc.update_id();
-                        match RouteGraph::from_adjlist(desc.adjlist, resman) {
-                            Ok(graph) => {
-                                // All outputs must be driven
-                                let are_outputs_driven = {
-                                    let mut real_outputs: Vec<u32> = graph.iter_outbound_edges()
-                                        .map(Edge::to_slot).collect();
-                                    real_outputs.sort();
+            if let Some(me) = Self::from_reader(id.name(), &path, reader, resman) {
+                resman.notify_effect_loaded(&path, me.meta.id.clone());
+                return Ok(me);
+            }
+        }
+        // No matching effects
+        Err(Error::NoMatchingEffect(id))
+    }
+    /// Like `from_id`, but load the effect from one specific file, regardless
+    /// of the id's hash.
+    pub fn from_path(id: EffectId, path: &Path, resman: &ResMan) -> ResultE<Rc<Self>> {
+        match File::open(path) {
+            Ok(reader) => {
+                if let Some(me) = Self::from_reader(id.name(), path, reader, resman) {
+                    resman.notify_effect_loaded(path, me.meta.id.clone());
+                    return Ok(me);
+                }
+            },
+            Err(error) => warn!("[{:?}] Unable to open effect file: {}", path, error),
+        }
+        Err(Error::NoMatchingEffect(id))
+    }
+    /// Try to decode the effect named `name` from the (file-like) `reader`.
+    /// On failure, the reason is logged and `None` is returned.
+    fn from_reader<Rd: Read>(name: &str, path: &Path, reader: Rd, resman: &ResMan) -> Option<Rc<Self>> {
+        // Try to deserialize to an effect description
+        let desc: Result<EffectDesc, serde_json::Error> = serde_json::from_reader(reader);
+        match desc {
+            Ok(desc) => Self::from_desc(name, &path, desc, resman),
+            Err(error) => {
+                warn!("[{:?}] Unable to deserialize EffectDesc: {:?}", path, error);
+                None
+            }
+        }
+    }
+    /// Try to instantiate the effect named `name` from its description.
+    /// `origin` describes where the description came from, for logging.
+    /// On failure, the reason is logged and `None` is returned.
+    fn from_desc(name: &str, origin: &fmt::Debug, mut desc: EffectDesc, resman: &ResMan) -> Option<Rc<Self>> {
+        if desc.meta.id.name() == name {
+            desc.update_id();
+            match RouteGraph::from_adjlist(desc.adjlist, resman) {
+                Ok(graph) => {
+                    // All outputs must be driven
+                    let are_outputs_driven = {
+                        let mut real_outputs: Vec<u32> = graph.iter_outbound_edges()
+                            .map(Edge::to_slot).collect();
+                        real_outputs.sort();
 
-                                    let exp_outputs = desc.meta.outputs().enumerate().map(|(i, _)| i as u32);
-                                    exp_outputs.eq(real_outputs)
-                                };
-                                // All input edges must also be declared in the metadata.
-                                // It's ok if an input declared in the metadata isn't actually used
-                                // anywhere, though.
-                                let are_inputs_valid = {
-                                    let mut exp_inputs = desc.meta.inputs();
-                                    // we allow up to 2**32 I/O; so ext_inputs.len() could return
-                                    // 2**32, which can't fit within usize on a 32-bit platform!
-                                    let max_input = exp_inputs.next().map(|_|
-                                        (exp_inputs.len() as u64) + 1).unwrap_or(0);
-                                    graph.iter_inbound_edges().all(|edge| {
-                                        (edge.from_slot() as u64) < max_input
-                                    })
-                                };
-                                let are_all_subnodes_driven = graph.iter_nodes().all(|(handle, node)| {
-                                    let mut driven_inputs: Vec<u32> = graph.iter_edges_to(handle)
-                                        .map(Edge::to_slot).collect();
-                                    driven_inputs.sort();
-                                    let exp_inputs = node.meta.inputs().enumerate().map(|(i, _)| i as u32);
-                                    exp_inputs.eq(driven_inputs)
-                                });
-                                if are_inputs_valid && are_outputs_driven && are_all_subnodes_driven {
-                                    let me = Self {
-                                        meta: desc.meta,
-                                        data: EffectData::RouteGraph(graph),
-                                    };
-                                    // TODO: implement some form of caching
-                                    return Ok(Rc::new(me));
-                                } else {
-                                    warn!("[{:?}] RouteGraph I/Os disagree with metadata", path)
-                                }
-                            },
-                            Err(error) => warn!("[{:?}] RouteGraph::from_adjlist failed: {:?}", path, error)
-                        }
+                        let exp_outputs = desc.meta.outputs().enumerate().map(|(i, _)| i as u32);
+                        exp_outputs.eq(real_outputs)
+                    };
+                    // All input edges must also be declared in the metadata.
+                    // It's ok if an input declared in the metadata isn't actually used
+                    // anywhere, though.
+                    let are_inputs_valid = {
+                        let mut exp_inputs = desc.meta.inputs();
+                        // we allow up to 2**32 I/O; so ext_inputs.len() could return
+                        // 2**32, which can't fit within usize on a 32-bit platform!
+                        let max_input = exp_inputs.next().map(|_|
+                            (exp_inputs.len() as u64) + 1).unwrap_or(0);
+                        graph.iter_inbound_edges().all(|edge| {
+                            (edge.from_slot() as u64) < max_input
+                        })
+                    };
+                    let are_all_subnodes_driven = graph.iter_nodes().all(|(handle, node)| {
+                        let mut driven_inputs: Vec<u32> = graph.iter_edges_to(handle)
+                            .map(Edge::to_slot).collect();
+                        driven_inputs.sort();
+                        let exp_inputs = node.meta.inputs().enumerate().map(|(i, _)| i as u32);
+                        exp_inputs.eq(driven_inputs)
+                    });
+                    if are_inputs_valid && are_outputs_driven && are_all_subnodes_driven {
+                        let me = Self {
+                            meta: desc.meta,
+                            data: EffectData::RouteGraph(graph),
+                        };
+                        // TODO: implement some form of caching
+                        Some(Rc::new(me))
                     } else {
-                        trace!("[{:?}] Effect names differ: wanted {:?} got {:?}", path, id.name(), desc.meta.id.name());
+                        warn!("[{:?}] RouteGraph I/Os disagree with metadata", origin);
+                        None
                     }
                 },
                 Err(error) => {
-                    warn!("[{:?}] Unable to deserialize EffectDesc: {:?}", path, error)
+                    warn!("[{:?}] RouteGraph::from_adjlist failed: {:?}", origin, error);
+                    None
                 }
             }
+        } else {
+            trace!("[{:?}] Effect names differ: wanted {:?} got {:?}", origin, name, desc.meta.id.name());
+            None
+        }
+    }
+    /// Return a copy of this effect in which every sub-effect (at any depth)
+    /// defined by `old` is replaced with `new`.
+    /// Returns `None` if this effect doesn't use `old` anywhere.
+    pub fn with_subeffect_replaced(&self, old: &EffectId, new: &Rc<Self>) -> routegraph::ResultE<Option<Rc<Self>>> {
+        match self.data {
+            EffectData::Primitive(_) => Ok(None),
+            EffectData::RouteGraph(ref graph) => {
+                let graph = graph.with_replaced_nodes(|data| {
+                    if data.id().same_definition(old) {
+                        Ok(Some(new.clone()))
+                    } else {
+                        data.with_subeffect_replaced(old, new)
+                    }
+                })?;
+                Ok(graph.map(|graph| Rc::new(Self {
+                    meta: self.meta.clone(),
+                    data: EffectData::RouteGraph(graph),
+                })))
+            }
         }
-        // No matching effects
-        Err(Error::NoMatchingEffect(id))
     }
     pub fn data(&self) -> &EffectData {
         &self.data
@@ -239,6 +314,10 @@ impl EffectId {
     pub fn sha256(&self) -> &Option<[u8; 32]> {
         &self.sha256
     }
+    /// Returns true if both ids refer to the same definition of the same effect.
+    pub fn same_definition(&self, other: &EffectId) -> bool {
+        self.name == other.name && self.sha256 == other.sha256
+    }
     /// Returns true if the effect cannot be decomposed.
     /// This is determined by the effect providing a SINGLE url, with the primitive:// Schema
     pub fn is_primitive(&self) -> bool {
@@ -246,6 +325,9 @@ impl EffectId {
             url.scheme() == "primitive"
         })
     }
+    pub fn urls<'a>(&'a self) -> impl Iterator<Item=&'a Url> + 'a {
+        self.urls.iter().map(|url| url.deref())
+    }
     pub fn get_primitive_url(&self) -> Option<&Url> {
         if self.is_primitive() {
             self.urls.iter().next().map(|url| url.deref())
@@ -309,6 +391,9 @@ impl EffectMeta {
                     EffectInput::new("source".into(), 0),
                     EffectInput::new("divisor".into(), 0),
                 ].into_iter()),
+            Some(PrimitiveEffect::Tanh) => Box::new(vec![
+                    EffectInput::new("source".into(), 0),
+                ].into_iter()),
             _ => Box::new(self.inputs.iter().cloned())
         }
     }
@@ -365,6 +450,7 @@ impl PrimitiveEffect {
                 "/Divide"      => Some(PrimitiveEffect::Divide),
                 "/Modulo"      => Some(PrimitiveEffect::Modulo),
                 "/Minimum"     => Some(PrimitiveEffect::Minimum),
+                "/Tanh"        => Some(PrimitiveEffect::Tanh),
                 _ => {
                     warn!("Unrecognized primitive effect: {} (full url: {})", url.path(), url);
                     None
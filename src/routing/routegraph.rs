@@ -8,6 +8,7 @@ use std::collections::hash_map;
 use std::collections::hash_set::HashSet;
 use std::fmt::{Display, Formatter};
 use std::fmt;
+use std::mem;
 use std::ops::Deref;
 use std::rc::Rc;
 
@@ -148,6 +149,12 @@ impl RouteGraph {
             node.inbound.iter()
         })
     }
+    /// Iterate over all the edges leading directly out of the given node.
+    pub fn iter_edges_from<'a>(&'a self, handle: &NodeHandle) -> impl Iterator<Item=&Edge> + 'a {
+        self.nodes.get(handle).into_iter().flat_map(|node| {
+            node.outbound.iter()
+        })
+    }
     /// Try to create a node with the given handle/data.
     /// Will error if the handle is already in use.
     pub fn add_node(&mut self, handle: NodeHandle, node_data: NodeData) -> ResultE<()> {
@@ -160,6 +167,75 @@ impl RouteGraph {
             },
         }
     }
+    /// Swap out the data associated with an existing node, keeping all its edges.
+    /// Will error (and leave the graph untouched) if the node's edges aren't valid
+    /// for the new data.
+    /// Returns the data that was previously associated with the node.
+    pub fn replace_node(&mut self, handle: NodeHandle, node_data: NodeData) -> ResultE<NodeData> {
+        let old_data = match self.nodes.get_mut(&handle).and_then(|node| node.node_data.as_mut()) {
+            None => return Err(Error::NoSuchNode),
+            Some(data) => mem::replace(data, node_data),
+        };
+        match self.validate_node_edges(&handle) {
+            Ok(()) => Ok(old_data),
+            Err(error) => {
+                // Restore the original data
+                self.nodes.get_mut(&handle).unwrap().node_data = Some(old_data);
+                Err(error)
+            }
+        }
+    }
+    /// Create a copy of this graph in which the data of each node is replaced
+    /// by whatever `f` returns for it (or kept as-is, if `f` returns `None`).
+    /// Returns `None` if `f` didn't replace any nodes.
+    pub fn with_replaced_nodes<F>(&self, mut f: F) -> ResultE<Option<Self>>
+        where F: FnMut(&NodeData) -> ResultE<Option<NodeData>>
+    {
+        let mut replaced = Vec::new();
+        let mut nodes = HashMap::new();
+        for (handle, node) in &self.nodes {
+            let node_data = match node.node_data {
+                Some(ref data) => match f(data)? {
+                    Some(new_data) => {
+                        replaced.push(*handle);
+                        Some(new_data)
+                    },
+                    None => Some(data.clone()),
+                },
+                None => None,
+            };
+            nodes.insert(*handle, Node {
+                outbound: node.outbound.clone(),
+                inbound: node.inbound.clone(),
+                node_data,
+            });
+        }
+        if replaced.is_empty() {
+            return Ok(None);
+        }
+        let me = Self { nodes };
+        for handle in &replaced {
+            me.validate_node_edges(handle)?;
+        }
+        Ok(Some(me))
+    }
+    /// Check that all edges attached to the node agree with the node's I/Os
+    /// and don't form any cycles.
+    fn validate_node_edges(&self, handle: &NodeHandle) -> ResultE<()> {
+        let node = &self.nodes[handle];
+        if let Some(data) = node.node_data.as_ref() {
+            let are_slots_valid = node.inbound.iter().all(|edge| data.meta().is_valid_input(edge.to_slot())) &&
+                node.outbound.iter().all(|edge| data.meta().is_valid_output(edge.from_slot()));
+            if !are_slots_valid {
+                return Err(Error::NoSuchSlot);
+            }
+        }
+        // The node's internal connectivity may have changed, so re-check for cycles.
+        if node.inbound.iter().any(|edge| self.is_edge_reachable(edge, edge)) {
+            return Err(Error::WouldCycle);
+        }
+        Ok(())
+    }
     /// Connect two nodes with an edge.
     /// Will return an error if the connection would violate any of the DAGs constraints.
     pub fn add_edge(&mut self, edge: Edge) -> ResultE<()> {
@@ -206,6 +282,19 @@ impl RouteGraph {
             Ok(())
         }
     }
+    /// Swap the `old` edge for the `new` one.
+    /// Will error (and leave the graph untouched) if `new` cannot be added.
+    pub fn replace_edge(&mut self, old: Edge, new: Edge) -> ResultE<()> {
+        self.del_edge(old.clone());
+        match self.add_edge(new) {
+            Ok(()) => Ok(()),
+            Err(error) => {
+                // Restore the original edge
+                self.add_edge_unchecked(old);
+                Err(error)
+            }
+        }
+    }
     /// Functionally equivalent to the `add_edge` method, but does not validate DAG constraints.
     fn add_edge_unchecked(&mut self, edge: Edge) {
         // associate the edge with its origin.
@@ -216,6 +305,12 @@ impl RouteGraph {
     /// Returns true if there is some directed path the connects `from` to `target`.
     /// Note that neither edge need currently exist in the graph.
     fn is_edge_reachable(&self, from: &Edge, target: &Edge) -> bool {
+        self.is_edge_reachable_helper(from, target, &mut HashSet::new())
+    }
+    /// Implementation of `is_edge_reachable`.
+    /// `visited` holds the edges that have already been searched from, so that
+    /// each is only searched once (and any unrelated cycles don't recurse forever).
+    fn is_edge_reachable_helper(&self, from: &Edge, target: &Edge, visited: &mut HashSet<Edge>) -> bool {
         // Algorithm:
         //   Try to reach `edge` from `edge`.
         //   If we reach the boundary of the DAG while doing so, consider all reachable outbound
@@ -223,11 +318,17 @@ impl RouteGraph {
         //     For each such edge, try to reach this DAG (recursively), and then resume the search for `edge`.
         if let Some(_to) = from.to.node_handle.get() {
             // The edge points to a NODE inside a DAG.
-            // Consider all (reachable) outgoing edges of the node:
             if let Some(node_data) = self.nodes.get(&from.to_full()) {
+                // If that's the node `target` leaves from, we've reached `target` if the two are
+                // connected through it. This works even if `target` isn't in the graph yet.
+                if from.to == target.from && self.are_edges_internally_connected(from, target) {
+                    return true;
+                }
+                // Consider all (reachable) outgoing edges of the node:
                 for candidate_edge in &node_data.outbound {
-                    if self.are_edges_internally_connected(from, candidate_edge) && 
-                      self.is_edge_reachable(candidate_edge, target) {
+                    if self.are_edges_internally_connected(from, candidate_edge) &&
+                      visited.insert(candidate_edge.clone()) &&
+                      self.is_edge_reachable_helper(candidate_edge, target, visited) {
                         return true;
                     }
                 }
@@ -275,6 +376,28 @@ impl RouteGraph {
             }
         }
     }
+    /// Delete the node along with every edge that leads into or out of it.
+    /// Returns the edges that were removed, so that watchers can be notified.
+    /// The graph is left untouched if this returns an error.
+    pub fn remove_node_cascade(&mut self, node: NodeHandle) -> ResultE<Vec<Edge>> {
+        // The toplevel I/O is not a real node and can never be deleted.
+        if node.is_toplevel() {
+            return Err(Error::NoSuchNode);
+        }
+        let edges: Vec<Edge> = match self.nodes.get(&node) {
+            // Already deleted
+            None => return Ok(Vec::new()),
+            Some(node_data) => node_data.inbound.iter()
+                .chain(node_data.outbound.iter())
+                .cloned()
+                .collect(),
+        };
+        for edge in &edges {
+            self.del_edge(edge.clone());
+        }
+        self.nodes.remove(&node);
+        Ok(edges)
+    }
     pub fn del_edge(&mut self, edge: Edge) {
         if let Some(edge_set) = self.nodes.get_mut(&edge.from_full()) {
             edge_set.outbound.remove(&edge);
@@ -0,0 +1,58 @@
+//! Mono master bus: sums every input down to one channel and soft-clips the
+//! result, so that the output is protected while monitoring.
+
+use url::Url;
+
+use routing::{EffectDesc, EffectInput, EffectMeta, EffectOutput};
+use super::GraphBuilder;
+
+/// Most inputs a master bus can be requested with through its url.
+/// Matches the 2^16 channel limit; anything larger is almost certainly a typo
+/// (or malicious), and would take forever to build.
+pub const MAX_INPUTS: u32 = 1 << 16;
+
+/// Create a master bus effect with `num_inputs` input slots and one output.
+/// The output is `tanh(drive * sum(inputs))`: unity gain around 0, saturating
+/// towards +/-1 without ever reaching it.
+pub fn masterbus(num_inputs: u32, drive: f32) -> EffectDesc {
+    let mut graph = GraphBuilder::default();
+
+    let inputs: Vec<_> = (0..num_inputs).map(|slot| graph.input(slot)).collect();
+    let sum = graph.sum(&inputs);
+    let drive_const = graph.constant(drive);
+    let driven = graph.binary("Multiply", sum, drive_const);
+    let clipped = graph.unary("Tanh", driven);
+    graph.output(clipped, 0);
+
+    let meta = EffectMeta::new("MasterBus".into(), vec![url(num_inputs, drive)],
+        (0..num_inputs).map(|slot| EffectInput::new(format!("source{}", slot), 0)).collect(),
+        vec![ EffectOutput::new("result".into(), 0) ],
+    );
+    EffectDesc::new(meta, graph.build())
+}
+
+/// Url through which the ResMan provides `masterbus(num_inputs, drive)`.
+pub fn url(num_inputs: u32, drive: f32) -> Url {
+    Url::parse(&format!("stdfx:///MasterBus?inputs={}&drive={}", num_inputs, drive)).unwrap()
+}
+
+/// Inverse of `url`. Returns `None` if any parameter is missing or malformed,
+/// or if more than `MAX_INPUTS` inputs are requested.
+pub fn from_url(url: &Url) -> Option<EffectDesc> {
+    let (mut num_inputs, mut drive) = (None, None);
+    for (key, value) in url.query_pairs() {
+        match key.as_ref() {
+            "inputs" => num_inputs = value.parse().ok(),
+            "drive" => drive = value.parse().ok(),
+            _ => warn!("Unrecognized MasterBus parameter: {} (full url: {})", key, url),
+        }
+    }
+    match (num_inputs, drive) {
+        (Some(num_inputs), Some(drive)) if num_inputs <= MAX_INPUTS => Some(masterbus(num_inputs, drive)),
+        (Some(num_inputs), Some(_)) => {
+            warn!("MasterBus cannot have more than {} inputs (requested {})", MAX_INPUTS, num_inputs);
+            None
+        },
+        _ => None,
+    }
+}
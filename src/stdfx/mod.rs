@@ -0,0 +1,103 @@
+//! Standard effects, built entirely out of primitive effects.
+//! Each effect is exposed as a function that returns its `EffectDesc`, and is
+//! registered with the `ResMan`, so that it can be instantiated through its
+//! `stdfx:///` url like any other effect.
+
+pub mod masterbus;
+
+// re-export the things we want public
+pub use self::masterbus::masterbus;
+
+use url::Url;
+
+use resman::ResMan;
+use routing::{AdjList, Edge, EdgeWeight, EffectId, NodeHandle};
+
+/// Make all the standard effects available through the ResMan.
+/// `Dispatch` does this for its own ResMan.
+pub fn register_all(resman: &mut ResMan) {
+    resman.register_std_effect("MasterBus", masterbus::from_url);
+}
+
+/// Some output slot of a node within the graph being built.
+/// If the node is toplevel, this refers to one of the effect's inputs.
+type Signal = (NodeHandle, u32);
+
+/// Helper for assembling the `AdjList` of an effect.
+#[derive(Default)]
+struct GraphBuilder {
+    nodes: Vec<(NodeHandle, EffectId)>,
+    edges: Vec<Edge>,
+    /// Shared F32Constant node used to source all constants.
+    const_hnd: Option<NodeHandle>,
+}
+
+impl GraphBuilder {
+    /// Add a primitive node (e.g. "Sum2") to the graph and return its handle.
+    fn add_prim(&mut self, name: &str) -> NodeHandle {
+        let handle = NodeHandle::new(self.nodes.len() as u32 + 1);
+        let url = Url::parse(&format!("primitive:///{}", name)).unwrap();
+        self.nodes.push((handle, EffectId::new(name.into(), None, vec![url])));
+        handle
+    }
+    /// Route `from` into the given input slot of `to`.
+    fn connect(&mut self, from: Signal, to: NodeHandle, to_slot: u32) {
+        let (from, from_slot) = from;
+        self.edges.push(Edge::new(from, to, EdgeWeight::new(from_slot, to_slot)));
+    }
+    /// Route `from` into the given output slot of the effect.
+    fn output(&mut self, from: Signal, slot: u32) {
+        self.connect(from, NodeHandle::toplevel(), slot);
+    }
+    /// Signal for the given input slot of the effect.
+    fn input(&self, slot: u32) -> Signal {
+        (NodeHandle::toplevel(), slot)
+    }
+    /// Signal which always carries `value`.
+    fn constant(&mut self, value: f32) -> Signal {
+        let const_hnd = match self.const_hnd {
+            Some(hnd) => hnd,
+            None => {
+                let hnd = self.add_prim("F32Constant");
+                self.const_hnd = Some(hnd);
+                hnd
+            }
+        };
+        // Float value is encoded via the slot.
+        (const_hnd, value.to_bits())
+    }
+    /// Feed `source` into slot 0 of a new primitive node, and return that
+    /// node's output.
+    fn unary(&mut self, name: &str, source: Signal) -> Signal {
+        let handle = self.add_prim(name);
+        self.connect(source, handle, 0);
+        (handle, 0)
+    }
+    /// Feed `left` and `right` into slots 0 and 1 of a new primitive node,
+    /// and return that node's output.
+    fn binary(&mut self, name: &str, left: Signal, right: Signal) -> Signal {
+        let handle = self.add_prim(name);
+        self.connect(left, handle, 0);
+        self.connect(right, handle, 1);
+        (handle, 0)
+    }
+    /// Sum all the signals using a balanced tree of Sum2 nodes.
+    fn sum(&mut self, signals: &[Signal]) -> Signal {
+        match signals.len() {
+            0 => self.constant(0f32),
+            1 => signals[0],
+            len => {
+                let (left, right) = signals.split_at(len/2);
+                let left = self.sum(left);
+                let right = self.sum(right);
+                self.binary("Sum2", left, right)
+            }
+        }
+    }
+    fn build(self) -> AdjList {
+        AdjList {
+            nodes: self.nodes,
+            edges: self.edges,
+        }
+    }
+}
@@ -0,0 +1,135 @@
+//! Test editing of the RouteGraph after nodes/edges have been created,
+//! through the Dispatch interface.
+
+extern crate jagged_array;
+extern crate libfriendship;
+#[macro_use] extern crate ndarray;
+extern crate url;
+
+use std::sync::mpsc::{channel, Receiver, Sender};
+
+use jagged_array::Jagged2Builder;
+use ndarray::Array2;
+use url::Url;
+
+use libfriendship::{Dispatch, Client};
+use libfriendship::dispatch::{OscRouteGraph, OscRenderer};
+use libfriendship::render::SparkleRenderer;
+use libfriendship::routing::{Edge, EdgeWeight, EffectId, NodeHandle};
+
+
+struct MyClient {
+    /// Where to send the rendered audio.
+    tx: Sender<Array2<f32>>,
+}
+impl Client for MyClient {
+    fn audio_rendered(&mut self, buffer: Array2<f32>, _idx: u64) {
+        self.tx.send(buffer).unwrap();
+    }
+}
+
+fn test_setup() -> (Dispatch<SparkleRenderer, MyClient>, Receiver<Array2<f32>>) {
+    let (tx, rx) = channel();
+    let dispatch = Dispatch::new(SparkleRenderer::default(), MyClient{ tx });
+    (dispatch, rx)
+}
+
+/// Return the `EffectId` that universally represents `F32Constant` nodes.
+fn const_id() -> EffectId {
+    EffectId::new("F32Constant".into(), None, vec![Url::parse("primitive:///F32Constant").unwrap()])
+}
+
+/// Return the `EffectId` that universally represents `Multiply` nodes.
+fn mult_id() -> EffectId {
+    EffectId::new("Multiply".into(), None, vec![Url::parse("primitive:///Multiply").unwrap()])
+}
+
+#[test]
+fn del_node_cascade() {
+    let (mut dispatch, rx) = test_setup();
+
+    // Create Multiply node (id=1)
+    let mult_hnd = NodeHandle::new(1);
+    dispatch.dispatch(OscRouteGraph::AddNode( (), (mult_hnd, mult_id()) ).into()).unwrap();
+    // Connect multiply output to master output.
+    dispatch.dispatch(OscRouteGraph::AddEdge(
+        (), (Edge::new_to_null(mult_hnd, EdgeWeight::new(0, 0)),)
+    ).into()).unwrap();
+
+    // Create Constant node (id=2)
+    let const_hnd = NodeHandle::new(2);
+    dispatch.dispatch(OscRouteGraph::AddNode( (), (const_hnd, const_id()) ).into()).unwrap();
+    // Route constant output to multiply input (A)
+    dispatch.dispatch(OscRouteGraph::AddEdge((), (Edge::new(const_hnd, mult_hnd, EdgeWeight::new((0.5f32).to_bits(), 0)),)).into()).unwrap();
+    // Route constant output to multiply input (B)
+    dispatch.dispatch(OscRouteGraph::AddEdge((), (Edge::new(const_hnd, mult_hnd, EdgeWeight::new((-3f32).to_bits(), 1)),)).into()).unwrap();
+
+    // Delete the multiply node and all three of its edges in one go.
+    dispatch.dispatch(OscRouteGraph::DelNodeCascade((), (mult_hnd,)).into()).unwrap();
+
+    // Nothing drives the output anymore.
+    dispatch.dispatch(
+        OscRenderer::RenderRange((), (0..4, 1, Default::default()))
+    .into()).unwrap();
+    let rendered = rx.recv().unwrap();
+    assert_eq!(rendered, array![[0f32, 0f32, 0f32, 0f32]]);
+
+    // The constant node no longer has edges, so a plain delete must succeed.
+    dispatch.dispatch(OscRouteGraph::DelNode((), (const_hnd,)).into()).unwrap();
+
+    // The handle and output slot are free again; reuse them.
+    dispatch.dispatch(OscRouteGraph::AddNode( (), (mult_hnd, const_id()) ).into()).unwrap();
+    dispatch.dispatch(OscRouteGraph::AddEdge(
+        (), (Edge::new_to_null(mult_hnd, EdgeWeight::new((2f32).to_bits(), 0)),)
+    ).into()).unwrap();
+    dispatch.dispatch(
+        OscRenderer::RenderRange((), (4..8, 1, Default::default()))
+    .into()).unwrap();
+    let rendered = rx.recv().unwrap();
+    assert_eq!(rendered, array![[2f32, 2f32, 2f32, 2f32]]);
+}
+
+#[test]
+fn set_param() {
+    let (mut dispatch, rx) = test_setup();
+
+    // Create Multiply node (id=1) to act as a gain stage.
+    let gain_hnd = NodeHandle::new(1);
+    dispatch.dispatch(OscRouteGraph::AddNode( (), (gain_hnd, mult_id()) ).into()).unwrap();
+    // Connect external input to multiply input (A)
+    dispatch.dispatch(OscRouteGraph::AddEdge(
+        (), (Edge::new_from_null(gain_hnd, EdgeWeight::new(0, 0)),)
+    ).into()).unwrap();
+    // Connect multiply output to master output.
+    dispatch.dispatch(OscRouteGraph::AddEdge(
+        (), (Edge::new_to_null(gain_hnd, EdgeWeight::new(0, 0)),)
+    ).into()).unwrap();
+
+    // Create Constant node (id=2) to hold the "gain" parameter.
+    let const_hnd = NodeHandle::new(2);
+    dispatch.dispatch(OscRouteGraph::AddNode( (), (const_hnd, const_id()) ).into()).unwrap();
+    // Route constant output to multiply input (B)
+    dispatch.dispatch(OscRouteGraph::AddEdge((), (Edge::new(const_hnd, gain_hnd, EdgeWeight::new((0.5f32).to_bits(), 1)),)).into()).unwrap();
+
+    // Render with gain = 0.5
+    let mut builder = Jagged2Builder::new();
+    builder.extend(&[1f32, 2f32, 3f32, 4f32]);
+    dispatch.dispatch(
+        OscRenderer::RenderRange((), (0..4, 1, builder.into()))
+    .into()).unwrap();
+    let rendered = rx.recv().unwrap();
+    assert_eq!(rendered, array![[0.5f32, 1f32, 1.5f32, 2f32]]);
+
+    // Change gain to 2.0 and render the same input again.
+    dispatch.dispatch(OscRouteGraph::SetParam((), (gain_hnd, 1, 2f32)).into()).unwrap();
+    let mut builder = Jagged2Builder::new();
+    builder.extend(&[1f32, 2f32, 3f32, 4f32]);
+    dispatch.dispatch(
+        OscRenderer::RenderRange((), (4..8, 1, builder.into()))
+    .into()).unwrap();
+    let rendered = rx.recv().unwrap();
+    assert_eq!(rendered, array![[2f32, 4f32, 6f32, 8f32]]);
+
+    // Slot 0 is fed by the external input, not a F32Constant.
+    assert!(dispatch.dispatch(OscRouteGraph::SetParam((), (gain_hnd, 0, 2f32)).into()).is_err());
+}
@@ -39,7 +39,8 @@ fn test_setup() -> (Dispatch<SparkleRenderer, MyClient>, Receiver<Array2<f32>>)
     (dispatch, rx)
 }
 
-fn create_multby2() -> EffectDesc {
+/// Create an effect named "MulBy2" that multiplies its input by `factor`.
+fn create_multby2(factor: f32) -> EffectDesc {
     let mult_hnd = NodeHandle::new(1);
     let mult_data = EffectId::new("Multiply".into(), None, vec![Url::parse("primitive:///Multiply").unwrap()]);
     let const_hnd = NodeHandle::new(2);
@@ -52,7 +53,7 @@ fn create_multby2() -> EffectDesc {
     // multiply out sent to effect out
     let edge_out = Edge::new_to_null(mult_hnd, EdgeWeight::new(0, 0));
     // const data sent to multiply (B)
-    let edge_const = Edge::new(const_hnd, mult_hnd, EdgeWeight::new((5.0f32).to_bits(), 1));
+    let edge_const = Edge::new(const_hnd, mult_hnd, EdgeWeight::new(factor.to_bits(), 1));
 
     let edges = vec![edge_in, edge_out, edge_const];
 
@@ -68,7 +69,7 @@ fn create_multby2() -> EffectDesc {
 fn load_multby2() {
     let (mut dispatch, rx) = test_setup();
     let dir = TempDir::new("libfriendship").unwrap();
-    let mulby2_desc = create_multby2();
+    let mulby2_desc = create_multby2(5.0f32);
 
     // Add the temp dir as a search dir
     dispatch.dispatch(
@@ -111,3 +112,229 @@ fn load_multby2() {
     assert_eq!(rendered, array![[2.5f32, 2.5f32, 2.5f32, 2.5f32]]);
 }
 
+
+#[test]
+fn reload_multby2() {
+    let (mut dispatch, rx) = test_setup();
+    let dir = TempDir::new("libfriendship").unwrap();
+
+    // Add the temp dir as a search dir
+    dispatch.dispatch(
+        OscResMan::AddDir((), (dir.path().to_str().unwrap().into(),)).into()
+    ).unwrap();
+
+    // Write the effect definition to file
+    let mulby2_path = dir.path().join("mulby2.fnd");
+    let mulby2_file = File::create(mulby2_path.clone()).unwrap();
+    serde_json::to_writer(mulby2_file, &create_multby2(5.0f32)).unwrap();
+
+    // Determine the hash of our file
+    let mut mulby2_file = File::open(mulby2_path.clone()).unwrap();
+    let hash_result = Sha256::digest_reader(&mut mulby2_file).unwrap();
+    let mut sha: [u8; 32] = Default::default();
+    sha.copy_from_slice(hash_result.as_slice());
+
+    // Create the MulBy2 node (id=1)
+    let mul_hnd = NodeHandle::new(1);
+    dispatch.dispatch(OscRouteGraph::AddNode((), (mul_hnd,
+        EffectId::new("MulBy2".into(), Some(sha), None)
+    )).into()).unwrap();
+    // Connect MulBy2 output to master output.
+    dispatch.dispatch(OscRouteGraph::AddEdge((), (Edge::new_to_null(mul_hnd, EdgeWeight::new(0, 0)),)).into()).unwrap();
+
+    // Create Constant node (id=2)
+    let const_hnd = NodeHandle::new(2);
+    dispatch.dispatch(OscRouteGraph::AddNode((), (const_hnd,
+        EffectId::new("Constant".into(), None, vec![Url::parse("primitive:///F32Constant").unwrap()])
+    )).into()).unwrap();
+    // Route constant output to mul input
+    dispatch.dispatch(OscRouteGraph::AddEdge((), (Edge::new(const_hnd, mul_hnd, EdgeWeight::new((0.5f32).to_bits(), 0)),)).into()).unwrap();
+
+    // This should be 0.5*5 = [2.5, 2.5, 2.5, 2.5]
+    dispatch.dispatch(
+        OscRenderer::RenderRange((), (0..4, 1, Default::default()))
+    .into()).unwrap();
+    let rendered = rx.recv().unwrap();
+    assert_eq!(rendered, array![[2.5f32, 2.5f32, 2.5f32, 2.5f32]]);
+
+    // Edit the effect on disk, and tell the resource manager about it.
+    let mulby2_file = File::create(mulby2_path.clone()).unwrap();
+    serde_json::to_writer(mulby2_file, &create_multby2(3.0f32)).unwrap();
+    dispatch.dispatch(
+        OscResMan::NotifyChanged((), (mulby2_path.to_str().unwrap().into(),)).into()
+    ).unwrap();
+
+    // The node should now use the new definition: 0.5*3 = [1.5, 1.5, 1.5, 1.5]
+    dispatch.dispatch(
+        OscRenderer::RenderRange((), (4..8, 1, Default::default()))
+    .into()).unwrap();
+    let rendered = rx.recv().unwrap();
+    assert_eq!(rendered, array![[1.5f32, 1.5f32, 1.5f32, 1.5f32]]);
+}
+
+/// Create an effect named "WrapMulBy2" whose only job is to route its input through "MulBy2".
+fn create_wrap_multby2() -> EffectDesc {
+    let inner_hnd = NodeHandle::new(1);
+    let inner_data = EffectId::new("MulBy2".into(), None, None);
+
+    let nodes = vec![(inner_hnd, inner_data)];
+
+    // input data sent to MulBy2
+    let edge_in = Edge::new_from_null(inner_hnd, EdgeWeight::new(0, 0));
+    // MulBy2 out sent to effect out
+    let edge_out = Edge::new_to_null(inner_hnd, EdgeWeight::new(0, 0));
+
+    let edges = vec![edge_in, edge_out];
+
+    let list = AdjList{ nodes, edges };
+    let meta = EffectMeta::new("WrapMulBy2".into(), None,
+        vec![ EffectInput::new("source".into(), 0) ],
+        vec![ EffectOutput::new("result".into(), 0) ],
+    );
+    EffectDesc::new(meta, list)
+}
+
+#[test]
+fn reload_nested_multby2() {
+    let (mut dispatch, rx) = test_setup();
+    let dir = TempDir::new("libfriendship").unwrap();
+
+    // Add the temp dir as a search dir
+    dispatch.dispatch(
+        OscResMan::AddDir((), (dir.path().to_str().unwrap().into(),)).into()
+    ).unwrap();
+
+    // Write both effect definitions to file
+    let mulby2_path = dir.path().join("mulby2.fnd");
+    let mulby2_file = File::create(mulby2_path.clone()).unwrap();
+    serde_json::to_writer(mulby2_file, &create_multby2(5.0f32)).unwrap();
+    let wrap_path = dir.path().join("wrapmulby2.fnd");
+    let wrap_file = File::create(wrap_path).unwrap();
+    serde_json::to_writer(wrap_file, &create_wrap_multby2()).unwrap();
+
+    // Create the WrapMulBy2 node (id=1)
+    let wrap_hnd = NodeHandle::new(1);
+    dispatch.dispatch(OscRouteGraph::AddNode((), (wrap_hnd,
+        EffectId::new("WrapMulBy2".into(), None, None)
+    )).into()).unwrap();
+    // Connect WrapMulBy2 output to master output.
+    dispatch.dispatch(OscRouteGraph::AddEdge((), (Edge::new_to_null(wrap_hnd, EdgeWeight::new(0, 0)),)).into()).unwrap();
+
+    // Create Constant node (id=2)
+    let const_hnd = NodeHandle::new(2);
+    dispatch.dispatch(OscRouteGraph::AddNode((), (const_hnd,
+        EffectId::new("Constant".into(), None, vec![Url::parse("primitive:///F32Constant").unwrap()])
+    )).into()).unwrap();
+    // Route constant output to WrapMulBy2 input
+    dispatch.dispatch(OscRouteGraph::AddEdge((), (Edge::new(const_hnd, wrap_hnd, EdgeWeight::new((0.5f32).to_bits(), 0)),)).into()).unwrap();
+
+    // This should be 0.5*5 = [2.5, 2.5, 2.5, 2.5]
+    dispatch.dispatch(
+        OscRenderer::RenderRange((), (0..4, 1, Default::default()))
+    .into()).unwrap();
+    let rendered = rx.recv().unwrap();
+    assert_eq!(rendered, array![[2.5f32, 2.5f32, 2.5f32, 2.5f32]]);
+
+    // Edit only the inner effect on disk, and tell the resource manager about it.
+    let mulby2_file = File::create(mulby2_path.clone()).unwrap();
+    serde_json::to_writer(mulby2_file, &create_multby2(3.0f32)).unwrap();
+    dispatch.dispatch(
+        OscResMan::NotifyChanged((), (mulby2_path.to_str().unwrap().into(),)).into()
+    ).unwrap();
+
+    // The outer node should now use the new inner definition: 0.5*3 = [1.5, 1.5, 1.5, 1.5]
+    dispatch.dispatch(
+        OscRenderer::RenderRange((), (4..8, 1, Default::default()))
+    .into()).unwrap();
+    let rendered = rx.recv().unwrap();
+    assert_eq!(rendered, array![[1.5f32, 1.5f32, 1.5f32, 1.5f32]]);
+}
+
+/// Create an effect named "Passthru". If `connected`, it outputs its input times 0.5.
+/// Otherwise, it ignores its input and outputs a constant 0.5.
+fn create_passthru(connected: bool) -> EffectDesc {
+    let const_hnd = NodeHandle::new(1);
+    let const_data = EffectId::new("Constant".into(), None, vec![Url::parse("primitive:///F32Constant").unwrap()]);
+    let (nodes, edges) = if connected {
+        let mult_hnd = NodeHandle::new(2);
+        let mult_data = EffectId::new("Multiply".into(), None, vec![Url::parse("primitive:///Multiply").unwrap()]);
+        (vec![(const_hnd, const_data), (mult_hnd, mult_data)], vec![
+            // input data sent to multiply (A)
+            Edge::new_from_null(mult_hnd, EdgeWeight::new(0, 0)),
+            // const data sent to multiply (B)
+            Edge::new(const_hnd, mult_hnd, EdgeWeight::new((0.5f32).to_bits(), 1)),
+            // multiply out sent to effect out
+            Edge::new_to_null(mult_hnd, EdgeWeight::new(0, 0)),
+        ])
+    } else {
+        (vec![(const_hnd, const_data)], vec![
+            // const data sent to effect out
+            Edge::new_to_null(const_hnd, EdgeWeight::new((0.5f32).to_bits(), 0)),
+        ])
+    };
+
+    let list = AdjList{ nodes, edges };
+    let meta = EffectMeta::new("Passthru".into(), None,
+        vec![ EffectInput::new("source".into(), 0) ],
+        vec![ EffectOutput::new("result".into(), 0) ],
+    );
+    EffectDesc::new(meta, list)
+}
+
+#[test]
+fn reload_rejects_cycle() {
+    let (mut dispatch, rx) = test_setup();
+    let dir = TempDir::new("libfriendship").unwrap();
+
+    // Add the temp dir as a search dir
+    dispatch.dispatch(
+        OscResMan::AddDir((), (dir.path().to_str().unwrap().into(),)).into()
+    ).unwrap();
+
+    // Write the effect definition to file; its input isn't connected to its output.
+    let passthru_path = dir.path().join("passthru.fnd");
+    let passthru_file = File::create(passthru_path.clone()).unwrap();
+    serde_json::to_writer(passthru_file, &create_passthru(false)).unwrap();
+
+    // Create the Passthru node (id=1)
+    let pass_hnd = NodeHandle::new(1);
+    dispatch.dispatch(OscRouteGraph::AddNode((), (pass_hnd,
+        EffectId::new("Passthru".into(), None, None)
+    )).into()).unwrap();
+    // Connect Passthru output to master output.
+    dispatch.dispatch(OscRouteGraph::AddEdge((), (Edge::new_to_null(pass_hnd, EdgeWeight::new(0, 0)),)).into()).unwrap();
+
+    // Create Multiply node (id=2), and Constant node (id=3) to drive its input (B)
+    let mult_hnd = NodeHandle::new(2);
+    dispatch.dispatch(OscRouteGraph::AddNode((), (mult_hnd,
+        EffectId::new("Multiply".into(), None, vec![Url::parse("primitive:///Multiply").unwrap()])
+    )).into()).unwrap();
+    let const_hnd = NodeHandle::new(3);
+    dispatch.dispatch(OscRouteGraph::AddNode((), (const_hnd,
+        EffectId::new("Constant".into(), None, vec![Url::parse("primitive:///F32Constant").unwrap()])
+    )).into()).unwrap();
+    dispatch.dispatch(OscRouteGraph::AddEdge((), (Edge::new(const_hnd, mult_hnd, EdgeWeight::new((2f32).to_bits(), 1)),)).into()).unwrap();
+    // Route Passthru -> Multiply -> Passthru. This isn't a cycle while Passthru ignores its input.
+    dispatch.dispatch(OscRouteGraph::AddEdge((), (Edge::new(pass_hnd, mult_hnd, EdgeWeight::new(0, 0)),)).into()).unwrap();
+    dispatch.dispatch(OscRouteGraph::AddEdge((), (Edge::new(mult_hnd, pass_hnd, EdgeWeight::new(0, 0)),)).into()).unwrap();
+
+    dispatch.dispatch(
+        OscRenderer::RenderRange((), (0..4, 1, Default::default()))
+    .into()).unwrap();
+    let rendered = rx.recv().unwrap();
+    assert_eq!(rendered, array![[0.5f32, 0.5f32, 0.5f32, 0.5f32]]);
+
+    // Edit the effect on disk so that it connects its input to its output, closing the loop.
+    let passthru_file = File::create(passthru_path.clone()).unwrap();
+    serde_json::to_writer(passthru_file, &create_passthru(true)).unwrap();
+    dispatch.dispatch(
+        OscResMan::NotifyChanged((), (passthru_path.to_str().unwrap().into(),)).into()
+    ).unwrap();
+
+    // The reload must be refused, leaving the old definition in place.
+    dispatch.dispatch(
+        OscRenderer::RenderRange((), (4..8, 1, Default::default()))
+    .into()).unwrap();
+    let rendered = rx.recv().unwrap();
+    assert_eq!(rendered, array![[0.5f32, 0.5f32, 0.5f32, 0.5f32]]);
+}
@@ -0,0 +1,83 @@
+//! Test per-node metering of rendered audio, through the Dispatch interface.
+
+extern crate libfriendship;
+#[macro_use] extern crate ndarray;
+extern crate url;
+
+use std::sync::mpsc::Receiver;
+
+use url::Url;
+
+use libfriendship::Dispatch;
+use libfriendship::client::{ClientMessage, MpscClient};
+use libfriendship::dispatch::{OscRouteGraph, OscRenderer};
+use libfriendship::render::{RefRenderer, Renderer, SparkleRenderer};
+use libfriendship::routing::{Edge, EdgeWeight, EffectId, NodeHandle};
+
+
+fn test_setup<R: Renderer>(renderer: R) -> (Dispatch<R, MpscClient>, Receiver<ClientMessage>) {
+    let (client, rx) = MpscClient::new();
+    let dispatch = Dispatch::new(renderer, client);
+    (dispatch, rx)
+}
+
+/// Return the `EffectId` that universally represents `F32Constant` nodes.
+fn const_id() -> EffectId {
+    EffectId::new("F32Constant".into(), None, vec![Url::parse("primitive:///F32Constant").unwrap()])
+}
+
+/// Return the `EffectId` that universally represents `Multiply` nodes.
+fn mult_id() -> EffectId {
+    EffectId::new("Multiply".into(), None, vec![Url::parse("primitive:///Multiply").unwrap()])
+}
+
+#[test]
+fn render_with_meters_sparkle() {
+    render_with_meters(SparkleRenderer::default());
+}
+
+#[test]
+fn render_with_meters_ref() {
+    render_with_meters(RefRenderer::default());
+}
+
+fn render_with_meters<R: Renderer>(renderer: R) {
+    let (mut dispatch, rx) = test_setup(renderer);
+
+    // Create Multiply node (id=1)
+    let mult_hnd = NodeHandle::new(1);
+    dispatch.dispatch(OscRouteGraph::AddNode( (), (mult_hnd, mult_id()) ).into()).unwrap();
+    // Connect multiply output to master output.
+    dispatch.dispatch(OscRouteGraph::AddEdge(
+        (), (Edge::new_to_null(mult_hnd, EdgeWeight::new(0, 0)),)
+    ).into()).unwrap();
+
+    // Create Constant node (id=2)
+    let const_hnd = NodeHandle::new(2);
+    dispatch.dispatch(OscRouteGraph::AddNode( (), (const_hnd, const_id()) ).into()).unwrap();
+    // Route constant output to multiply input (A)
+    dispatch.dispatch(OscRouteGraph::AddEdge((), (Edge::new(const_hnd, mult_hnd, EdgeWeight::new((-0.75f32).to_bits(), 0)),)).into()).unwrap();
+    // Route constant output to multiply input (B)
+    dispatch.dispatch(OscRouteGraph::AddEdge((), (Edge::new(const_hnd, mult_hnd, EdgeWeight::new((2f32).to_bits(), 1)),)).into()).unwrap();
+
+    dispatch.dispatch(
+        OscRenderer::RenderWithMeters((), (0..4, 1, Default::default()))
+    .into()).unwrap();
+
+    // The audio is delivered as usual: -0.75 * 2.0 = -1.5
+    match rx.recv().unwrap() {
+        ClientMessage::AudioRendered(rendered, 0) => {
+            assert_eq!(rendered, array![[-1.5f32, -1.5f32, -1.5f32, -1.5f32]]);
+        },
+        msg => panic!("Expected rendered audio, got {:?}", msg),
+    }
+    // The constant node's largest output is 2.0; the multiply's is |-1.5|.
+    match rx.recv().unwrap() {
+        ClientMessage::NodeMeters(meters) => {
+            assert_eq!(meters.len(), 2);
+            assert_eq!(meters[&const_hnd], 2f32);
+            assert_eq!(meters[&mult_hnd], 1.5f32);
+        },
+        msg => panic!("Expected node meters, got {:?}", msg),
+    }
+}
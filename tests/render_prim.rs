@@ -11,7 +11,7 @@ use url::Url;
 
 use libfriendship::{Dispatch, Client};
 use libfriendship::dispatch::{OscRouteGraph, OscRenderer};
-use libfriendship::render::SparkleRenderer;
+use libfriendship::render::{RefRenderer, Renderer, SparkleRenderer};
 use libfriendship::routing::{Edge, EdgeWeight, EffectId, NodeHandle};
 
 
@@ -66,6 +66,11 @@ fn min_id() -> EffectId {
     EffectId::new("Minimum".into(), None, vec![Url::parse("primitive:///Minimum").unwrap()])
 }
 
+/// Return the `EffectId` that universally represents `Tanh` nodes.
+fn tanh_id() -> EffectId {
+    EffectId::new("Tanh".into(), None, vec![Url::parse("primitive:///Tanh").unwrap()])
+}
+
 #[test]
 fn render_zeros() {
     let (mut dispatch, rx) = test_setup();
@@ -289,3 +294,40 @@ fn render_min() {
     let exp = -3.5f32;
     assert_eq!(rendered, array![[exp, exp, exp, exp]]);
 }
+
+#[test]
+fn render_tanh() {
+    let (dispatch, rx) = test_setup();
+    check_tanh(dispatch, rx);
+}
+
+#[test]
+fn render_tanh_ref() {
+    let (tx, rx) = channel();
+    let dispatch = Dispatch::new(RefRenderer::default(), MyClient{ tx });
+    check_tanh(dispatch, rx);
+}
+
+fn check_tanh<R: Renderer>(mut dispatch: Dispatch<R, MyClient>, rx: Receiver<Array2<f32>>) {
+
+    // Create Tanh node (id=1)
+    let tanh_hnd = NodeHandle::new(1);
+    dispatch.dispatch(OscRouteGraph::AddNode( (), (tanh_hnd, tanh_id()) ).into()).unwrap();
+    // Connect tanh output to master output.
+    dispatch.dispatch(OscRouteGraph::AddEdge((), (Edge::new_to_null(tanh_hnd, EdgeWeight::new(0, 0)),)).into()).unwrap();
+
+    // Create Constant node (id=2)
+    let const_hnd = NodeHandle::new(2);
+    dispatch.dispatch(OscRouteGraph::AddNode( (), (const_hnd, const_id()) ).into()).unwrap();
+    // Route constant output to tanh input
+    dispatch.dispatch(OscRouteGraph::AddEdge((), (Edge::new(const_hnd, tanh_hnd, EdgeWeight::new((0.5f32).to_bits(), 0)),)).into()).unwrap();
+
+    // Read some data from ch=0.
+    // This should be tanh(0.5)
+    dispatch.dispatch(
+        OscRenderer::RenderRange((), (0..4, 1, Default::default()))
+    .into()).unwrap();
+    let rendered = rx.recv().unwrap();
+    let exp = (0.5f32).tanh();
+    assert_eq!(rendered, array![[exp, exp, exp, exp]]);
+}
@@ -0,0 +1,79 @@
+//! Test rendering of the standard effects, through the Dispatch interface.
+
+extern crate jagged_array;
+extern crate libfriendship;
+#[macro_use] extern crate ndarray;
+
+use std::sync::mpsc::{channel, Receiver, Sender};
+
+use jagged_array::Jagged2Builder;
+use ndarray::Array2;
+
+use libfriendship::{Dispatch, Client};
+use libfriendship::dispatch::{OscRouteGraph, OscRenderer};
+use libfriendship::render::SparkleRenderer;
+use libfriendship::routing::{Edge, EdgeWeight, EffectId, NodeHandle};
+use libfriendship::stdfx;
+
+
+struct MyClient {
+    /// Where to send the rendered audio.
+    tx: Sender<Array2<f32>>,
+}
+impl Client for MyClient {
+    fn audio_rendered(&mut self, buffer: Array2<f32>, _idx: u64) {
+        self.tx.send(buffer).unwrap();
+    }
+}
+
+fn test_setup() -> (Dispatch<SparkleRenderer, MyClient>, Receiver<Array2<f32>>) {
+    let (tx, rx) = channel();
+    let dispatch = Dispatch::new(SparkleRenderer::default(), MyClient{ tx });
+    (dispatch, rx)
+}
+
+#[test]
+fn render_masterbus() {
+    let (mut dispatch, rx) = test_setup();
+
+    // Create MasterBus node (id=1); the ResMan provides it, so no files are needed.
+    let bus_hnd = NodeHandle::new(1);
+    dispatch.dispatch(OscRouteGraph::AddNode((), (bus_hnd,
+        EffectId::new("MasterBus".into(), None, vec![stdfx::masterbus::url(3, 2f32)])
+    )).into()).unwrap();
+    // Connect bus output to master output.
+    dispatch.dispatch(OscRouteGraph::AddEdge((), (Edge::new_to_null(bus_hnd, EdgeWeight::new(0, 0)),)).into()).unwrap();
+    // Connect external inputs 0, 1, 2 to the bus.
+    for slot in 0..3 {
+        dispatch.dispatch(OscRouteGraph::AddEdge((),
+            (Edge::new_from_null(bus_hnd, EdgeWeight::new(slot, slot)),)
+        ).into()).unwrap();
+    }
+
+    let mut builder = Jagged2Builder::new();
+    builder.extend(&[0.5f32, 0.5f32, 0.5f32, 0.5f32]);
+    builder.extend(&[0.25f32, 0.25f32, 0.25f32, 0.25f32]);
+    builder.extend(&[0.75f32, 0.75f32, 0.75f32, 0.75f32]);
+    dispatch.dispatch(
+        OscRenderer::RenderRange((), (0..4, 1, builder.into()))
+    .into()).unwrap();
+    let rendered = rx.recv().unwrap();
+
+    // Sum = 1.5, driven = 3.0, which soft-clips to tanh(3.0)
+    let exp = (3f32).tanh();
+    assert_eq!(rendered, array![[exp, exp, exp, exp]]);
+    assert!(rendered.iter().all(|&sample| sample < 1f32));
+}
+
+#[test]
+fn reject_huge_masterbus() {
+    let (mut dispatch, _rx) = test_setup();
+
+    // Building this many inputs would never finish, so the ResMan must refuse it.
+    let huge_id = EffectId::new("MasterBus".into(), None, vec![stdfx::masterbus::url(u32::max_value(), 1f32)]);
+    assert!(dispatch.dispatch(OscRouteGraph::AddNode((), (NodeHandle::new(1), huge_id)).into()).is_err());
+
+    // Anything past the limit is refused, not just absurd values.
+    let past_limit_url = stdfx::masterbus::url(stdfx::masterbus::MAX_INPUTS + 1, 1f32);
+    assert!(stdfx::masterbus::from_url(&past_limit_url).is_none());
+}
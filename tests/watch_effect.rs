@@ -0,0 +1,128 @@
+//! Test that effects are reloaded when their files change on disk,
+//! without the host having to report the change.
+//! Requires the `notify` feature.
+
+#[macro_use] extern crate libfriendship;
+#[macro_use] extern crate ndarray;
+extern crate serde_json;
+extern crate tempdir;
+extern crate url;
+
+use std::fs::File;
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::thread;
+use std::time::Duration;
+
+use ndarray::Array2;
+use tempdir::TempDir;
+use url::Url;
+
+use libfriendship::{Dispatch, Client};
+use libfriendship::dispatch::{OscRouteGraph, OscRenderer, OscResMan};
+use libfriendship::render::SparkleRenderer;
+use libfriendship::routing::{NodeHandle, Edge, EdgeWeight, EffectId, EffectDesc, EffectMeta, EffectInput, EffectOutput};
+use libfriendship::routing::AdjList;
+
+struct MyClient {
+    /// Where to send the rendered audio.
+    tx: Sender<Array2<f32>>,
+}
+impl Client for MyClient {
+    fn audio_rendered(&mut self, buffer: Array2<f32>, _idx: u64) {
+        self.tx.send(buffer).unwrap();
+    }
+}
+
+fn test_setup() -> (Dispatch<SparkleRenderer, MyClient>, Receiver<Array2<f32>>) {
+    let (tx, rx) = channel();
+    let dispatch = Dispatch::new(SparkleRenderer::default(), MyClient{ tx });
+    (dispatch, rx)
+}
+
+/// Create an effect named "MulBy2" that multiplies its input by `factor`.
+fn create_multby2(factor: f32) -> EffectDesc {
+    let mult_hnd = NodeHandle::new(1);
+    let mult_data = EffectId::new("Multiply".into(), None, vec![Url::parse("primitive:///Multiply").unwrap()]);
+    let const_hnd = NodeHandle::new(2);
+    let const_data = EffectId::new("Constant".into(), None, vec![Url::parse("primitive:///F32Constant").unwrap()]);
+
+    let nodes = vec![(mult_hnd, mult_data), (const_hnd, const_data)];
+
+    // input data sent to multiply (A)
+    let edge_in = Edge::new_from_null(mult_hnd, EdgeWeight::new(0, 0));
+    // multiply out sent to effect out
+    let edge_out = Edge::new_to_null(mult_hnd, EdgeWeight::new(0, 0));
+    // const data sent to multiply (B)
+    let edge_const = Edge::new(const_hnd, mult_hnd, EdgeWeight::new(factor.to_bits(), 1));
+
+    let edges = vec![edge_in, edge_out, edge_const];
+
+    let list = AdjList{ nodes, edges };
+    let meta = EffectMeta::new("MulBy2".into(), None,
+        vec![ EffectInput::new("source".into(), 0) ],
+        vec![ EffectOutput::new("result".into(), 0) ],
+    );
+    EffectDesc::new(meta, list)
+}
+
+#[test]
+fn watch_multby2() {
+    let (mut dispatch, rx) = test_setup();
+    // Note: the temp dir may be reached through a symlink (e.g. /tmp on macOS),
+    // so the paths reported by the OS won't necessarily match the search dir verbatim.
+    let dir = TempDir::new("libfriendship").unwrap();
+
+    // Add the temp dir as a search dir
+    dispatch.dispatch(
+        OscResMan::AddDir((), (dir.path().to_str().unwrap().into(),)).into()
+    ).unwrap();
+
+    // Write the effect definition to file
+    let mulby2_path = dir.path().join("mulby2.fnd");
+    let mulby2_file = File::create(mulby2_path.clone()).unwrap();
+    serde_json::to_writer(mulby2_file, &create_multby2(5.0f32)).unwrap();
+
+    // Create the MulBy2 node (id=1)
+    let mul_hnd = NodeHandle::new(1);
+    dispatch.dispatch(OscRouteGraph::AddNode((), (mul_hnd,
+        EffectId::new("MulBy2".into(), None, None)
+    )).into()).unwrap();
+    // Connect MulBy2 output to master output.
+    dispatch.dispatch(OscRouteGraph::AddEdge((), (Edge::new_to_null(mul_hnd, EdgeWeight::new(0, 0)),)).into()).unwrap();
+
+    // Create Constant node (id=2)
+    let const_hnd = NodeHandle::new(2);
+    dispatch.dispatch(OscRouteGraph::AddNode((), (const_hnd,
+        EffectId::new("Constant".into(), None, vec![Url::parse("primitive:///F32Constant").unwrap()])
+    )).into()).unwrap();
+    // Route constant output to mul input
+    dispatch.dispatch(OscRouteGraph::AddEdge((), (Edge::new(const_hnd, mul_hnd, EdgeWeight::new((0.5f32).to_bits(), 0)),)).into()).unwrap();
+
+    // This should be 0.5*5 = [2.5, 2.5, 2.5, 2.5]
+    dispatch.dispatch(
+        OscRenderer::RenderRange((), (0..4, 1, Default::default()))
+    .into()).unwrap();
+    let rendered = rx.recv().unwrap();
+    assert_eq!(rendered, array![[2.5f32, 2.5f32, 2.5f32, 2.5f32]]);
+
+    // Edit the effect on disk, but DON'T tell the resource manager about it.
+    let mulby2_file = File::create(mulby2_path.clone()).unwrap();
+    serde_json::to_writer(mulby2_file, &create_multby2(3.0f32)).unwrap();
+
+    // Changes are picked up whenever a message is dispatched; keep rendering
+    // until the new definition is in use: 0.5*3 = [1.5, 1.5, 1.5, 1.5]
+    let expected = array![[1.5f32, 1.5f32, 1.5f32, 1.5f32]];
+    let mut rendered = Array2::zeros((1, 4));
+    for attempt in 1..50 {
+        thread::sleep(Duration::from_millis(100));
+        let start = 4*attempt;
+        dispatch.dispatch(
+            OscRenderer::RenderRange((), (start..start+4, 1, Default::default()))
+        .into()).unwrap();
+        rendered = rx.recv().unwrap();
+        if rendered == expected {
+            break;
+        }
+    }
+    assert_eq!(rendered, expected);
+}